@@ -0,0 +1,137 @@
+// src/config.rs
+// standard library
+use std::env;
+use std::fmt::Display;
+use std::io;
+use std::str::FromStr;
+// third-party dependencies
+use serde::Serialize;
+
+// Server settings read from the environment, serialized fields are public via GET /capabilities so secrets must be skipped
+#[derive(Clone, Serialize)]
+pub struct ServerConfig {
+    pub request_timeout_secs: u64,
+    pub max_upload_bytes: usize,
+    pub max_batch_upload_bytes: usize,
+    pub max_import_bytes: usize,
+    pub max_import_uncompressed_bytes: u64,
+    pub rate_limit_rpm: u32,
+    pub log_format: LogFormat,
+    #[serde(skip)]
+    pub tls_cert_path: Option<String>,
+    #[serde(skip)]
+    pub tls_key_path: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected \"text\" or \"json\"".to_string()),
+        }
+    }
+}
+
+// Settings used when the environment leaves them unset
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            request_timeout_secs: 30,
+            max_upload_bytes: 5 * 1024 * 1024,
+            max_batch_upload_bytes: 20 * 1024 * 1024,
+            max_import_bytes: 20 * 1024 * 1024,
+            max_import_uncompressed_bytes: 100 * 1024 * 1024, // guards against archives that expand far beyond their upload size
+            rate_limit_rpm: 0, // zero disables rate limiting
+            log_format: LogFormat::Text,
+            tls_cert_path: None,
+            tls_key_path: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    // Reads every setting, failing on values that can't be parsed or combinations that can't be served
+    pub fn from_env() -> io::Result<Self> {
+        let tls_cert_path = env::var("TLS_CERT_PATH").ok();
+        let tls_key_path = env::var("TLS_KEY_PATH").ok();
+        if tls_cert_path.is_some() != tls_key_path.is_some() {
+            return Err(io::Error::other("TLS_CERT_PATH and TLS_KEY_PATH must be set together to enable TLS"));
+        }
+
+        let defaults = ServerConfig::default();
+
+        Ok(ServerConfig {
+            request_timeout_secs: env_or("REQUEST_TIMEOUT_SECS", defaults.request_timeout_secs)?,
+            max_upload_bytes: env_or("MAX_UPLOAD_BYTES", defaults.max_upload_bytes)?,
+            max_batch_upload_bytes: env_or("MAX_BATCH_UPLOAD_BYTES", defaults.max_batch_upload_bytes)?,
+            max_import_bytes: env_or("MAX_IMPORT_BYTES", defaults.max_import_bytes)?,
+            max_import_uncompressed_bytes: env_or("MAX_IMPORT_UNCOMPRESSED_BYTES", defaults.max_import_uncompressed_bytes)?,
+            rate_limit_rpm: env_or("RATE_LIMIT_RPM", defaults.rate_limit_rpm)?,
+            log_format: env_or("LOG_FORMAT", defaults.log_format)?,
+            tls_cert_path,
+            tls_key_path,
+        })
+    }
+}
+
+// Reads a value from the environment, falling back to the default only when it is unset
+fn env_or<T: FromStr>(key: &str, default: T) -> io::Result<T>
+where
+    T::Err: Display,
+{
+    match env::var(key) {
+        Ok(value) => parse_setting(key, &value),
+        Err(env::VarError::NotPresent) => Ok(default),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid value for {}: {}", key, e))),
+    }
+}
+
+// Parses a setting's value, naming the setting in the error so a typo is easy to find at startup
+fn parse_setting<T: FromStr>(key: &str, value: &str) -> io::Result<T>
+where
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid value {:?} for {}: {}", value, key, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_settings() {
+        assert_eq!(parse_setting::<usize>("MAX_UPLOAD_BYTES", "5242880").unwrap(), 5242880);
+        assert_eq!(parse_setting::<u32>("RATE_LIMIT_RPM", "0").unwrap(), 0);
+        assert!(parse_setting::<LogFormat>("LOG_FORMAT", "JSON").unwrap() == LogFormat::Json);
+        assert!(parse_setting::<LogFormat>("LOG_FORMAT", "text").unwrap() == LogFormat::Text);
+    }
+
+    #[test]
+    fn rejects_unparsable_settings_naming_the_variable() {
+        let error = parse_setting::<usize>("MAX_UPLOAD_BYTES", "5MB").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("MAX_UPLOAD_BYTES"));
+        assert!(error.to_string().contains("5MB"));
+
+        assert!(parse_setting::<u32>("RATE_LIMIT_RPM", "-1").is_err());
+        assert!(parse_setting::<u64>("REQUEST_TIMEOUT_SECS", "").is_err());
+        assert!(parse_setting::<LogFormat>("LOG_FORMAT", "jsn").is_err());
+    }
+
+    #[test]
+    fn unset_settings_use_the_default() {
+        assert_eq!(env_or("FORSETI_TEST_UNSET_SETTING", 42u32).unwrap(), 42);
+    }
+}
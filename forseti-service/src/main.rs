@@ -1,22 +1,132 @@
+// standard library
+use std::io::{self, IsTerminal};
+use std::sync::Arc;
+use std::time::Duration;
 //Third-party-dependencies
-use actix_web::{App, HttpServer};
-use routes::file_routes;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::middleware::Next;
+use actix_web::{middleware, web, App, Error, HttpServer};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use config::{LogFormat, ServerConfig};
+use routes::{file_routes, search_routes, server_routes};
+use utils::error_handlers::{self, path_error_handler, query_error_handler};
+use utils::rate_limiter::RateLimiter;
 
 // Module imports:
+mod config;
 mod routes;
+mod utils;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // address the server will run on
     let address = "127.0.0.1:9090"; //TODO: implement server hosted envirment variable
-    println!("Server started at {}", address);
+    let config = ServerConfig::from_env()?;
+    init_logging(config.log_format);
     std::fs::create_dir_all("./storage")?; //TODO: Review stored files
-    HttpServer::new(|| {
+    let rate_limiter = RateLimiter::new(config.rate_limit_rpm); // shared so the limit applies across all workers
+    let request_timeout = Duration::from_secs(config.request_timeout_secs);
+    let app_config = config.clone();
+    let server = HttpServer::new(move || {
         App::new()
-            .configure(file_routes::init_routes) // utilises methods from routes
-    })
-        .bind(address)?
-        .run()
-        .await
+            .wrap(middleware::from_fn(move |req, next| enforce_request_timeout(req, next, request_timeout)))
+            .wrap(rate_limiter.clone()) // throttles clients exceeding RATE_LIMIT_RPM, disabled when unset
+            .wrap(middleware::Logger::default()) // logs each request through the configured log format
+            .wrap(middleware::Compress::default()) // compresses responses according to the client's Accept-Encoding
+            .app_data(web::Data::new(app_config.clone()))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler)) // bad query strings get the same JSON body as other 400s
+            .app_data(web::PathConfig::default().error_handler(path_error_handler))
+            .configure(|cfg| file_routes::init_routes(cfg, &app_config)) // utilises methods from routes
+            .configure(search_routes::init_routes)
+            .configure(server_routes::init_routes)
+    }); // request headers keep actix's own read timeout, REQUEST_TIMEOUT_SECS only bounds handling
+
+    // Terminate TLS in-process when a certificate and key are configured, otherwise serve plain HTTP
+    match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let tls_config = load_tls_config(cert_path, key_path)?;
+            tracing::info!("Server started at https://{}", address);
+            server.bind_rustls_0_23(address, tls_config)?.run().await
+        }
+        _ => {
+            tracing::info!("Server started at http://{}", address);
+            server.bind(address)?.run().await
+        }
+    }
+}
+
+// Builds the rustls server config from PEM encoded certificate chain and private key files
+fn load_tls_config(cert_path: &str, key_path: &str) -> io::Result<rustls::ServerConfig> {
+    let cert_chain = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| io::Error::other(format!("Failed to load TLS certificate from '{}': {}", cert_path, e)))?;
+    let private_key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| io::Error::other(format!("Failed to load TLS private key from '{}': {}", key_path, e)))?;
+
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(cert_chain, private_key))
+        .map_err(|e| io::Error::other(format!("Invalid TLS configuration: {}", e)))
+}
+
+// Fails requests whose handling, including reading the body, takes longer than the timeout
+async fn enforce_request_timeout(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+    timeout: Duration,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    match actix_web::rt::time::timeout(timeout, next.call(req)).await {
+        Ok(response) => response,
+        Err(_) => Err(InternalError::from_response("Request timed out", error_handlers::request_timeout("Request timed out")).into()),
+    }
+}
+
+// Logs as JSON objects when LOG_FORMAT=json, otherwise as human readable text
+fn init_logging(log_format: LogFormat) {
+    if log_format == LogFormat::Json {
+        tracing_subscriber::fmt().json().init();
+    } else {
+        // Colours only when a terminal is attached, so redirected logs aren't littered with escape codes
+        tracing_subscriber::fmt().with_ansi(io::stdout().is_terminal()).init();
+    }
 }
 //TEST: Launch server on http://127.0.0.1:9090/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::test as actix_test;
+
+    #[actix_web::test]
+    async fn slow_handlers_time_out_with_a_json_408() {
+        let timeout = Duration::from_millis(100);
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(middleware::from_fn(move |req, next| enforce_request_timeout(req, next, timeout)))
+                .route("/fast", web::get().to(|| async { actix_web::HttpResponse::Ok().finish() }))
+                .route(
+                    "/slow",
+                    web::get().to(|| async {
+                        actix_web::rt::time::sleep(Duration::from_secs(1)).await;
+                        actix_web::HttpResponse::Ok().finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/fast").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let resp = match actix_test::try_call_service(&app, actix_test::TestRequest::get().uri("/slow").to_request()).await {
+            Ok(_) => panic!("slow handler should have timed out"),
+            Err(err) => err.error_response(),
+        };
+        assert_eq!(resp.status(), actix_web::http::StatusCode::REQUEST_TIMEOUT);
+        let body: serde_json::Value = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(body["error"], "request_timeout");
+    }
+}
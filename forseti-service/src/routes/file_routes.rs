@@ -1,12 +1,77 @@
 // src/routes/file_routes.rs
 // standard library
 use std::fs;
-use std::io;
+use std::io::{self, Read};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
 // third-party dependencies
-use actix_web::{App, get, post, web, HttpResponse, Responder};
+use actix_web::{body::SizedStream, get, head, post, web, HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use actix_web::http::header::{self, ContentDisposition, ContentEncoding, ETag, EntityTag, IfNoneMatch};
+use actix_web::http::StatusCode;
+use actix_web::middleware::ErrorHandlers;
+use futures_util::stream;
+use pulldown_cmark::{Options, Parser};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sha2::{Digest, Sha256};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 use std::path::Path;
-const STORAGE_PATH: &str = "./storage"; //TODO: Implement group specifc file paths
+// local modules
+use crate::config::ServerConfig;
+use crate::utils::error_handlers::{self, json_error_handler};
+use crate::utils::fs_utils;
+pub(crate) const STORAGE_PATH: &str = "./storage"; //TODO: Implement group specifc file paths
+// Types a browser would run scripts from if it displayed them inline
+const ACTIVE_CONTENT_TYPES: [&str; 5] = ["text/html", "application/xhtml+xml", "image/svg+xml", "text/xml", "application/xml"];
+
+// Query parameters for rendering a file to HTML
+#[derive(Deserialize)]
+struct RenderQuery {
+    #[serde(default)]
+    sanitize: bool,
+}
+
+// Query parameters for listing files changed since a point in time
+#[derive(Deserialize)]
+struct ModifiedSinceQuery {
+    timestamp: String,
+}
+
+// A file changed since the requested time
+#[derive(Serialize)]
+struct ModifiedFile {
+    filename: String,
+    last_modified: String,
+}
+
+// Request body for uploading several files at once
+#[derive(Deserialize)]
+struct BatchUploadRequest {
+    files: Vec<BatchUploadFile>,
+}
+
+#[derive(Deserialize)]
+struct BatchUploadFile {
+    file_name: String,
+    file_content: String,
+}
+
+// Query parameters for importing a ZIP archive
+#[derive(Deserialize)]
+struct ImportQuery {
+    #[serde(default)]
+    overwrite: bool,
+}
+
+// Outcome of a single file within a batch upload or import
+#[derive(Serialize)]
+struct BatchUploadResult {
+    filename: String,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
 
 
 // GET ROUTES
@@ -16,16 +81,68 @@ async fn index() -> impl Responder {
 }
 
 // Pulls files down from server
-#[get("/files/{filename}")]
-async fn get_file(path: web::Path<String>) -> impl Responder {
-    let filename = path.into_inner();
-    let filepath = format!("{}/{}", STORAGE_PATH, filename); // Save the file as a named file to the storage path //TODO: Implement group specifc file paths
+#[get("/files/{filename:.*}")]
+async fn get_file(req: HttpRequest, path: web::Path<String>) -> impl Responder {
+    let filename = match fs_utils::sanitize_filename(&path.into_inner()) {
+        Ok(name) => name,
+        Err(e) => return error_handlers::bad_request(e),
+    };
+
+    // Read the file as raw bytes so binary files download as they were uploaded
+    let content = match read_file_bytes(&filename) {
+        Ok(content) => content,
+        Err(_) => return HttpResponse::NotFound().body("File not found"),
+    };
+
+    // Tag the content with its hash, skipping the body if the client already holds this version
+    let etag = content_etag(&content);
+    let unchanged = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+    if unchanged {
+        return HttpResponse::NotModified().insert_header(ETag(etag)).finish();
+    }
+
+    let mut response = HttpResponse::Ok();
+    insert_content_headers(&mut response, &filename);
+    response.insert_header(ETag(etag)).body(content)
+}
+
+// Renders a markdown file to HTML for previewing
+// Kept off the /files prefix so a file stored as "<folder>/render" can still be downloaded
+#[get("/render/{filename:.*}")]
+async fn render_file(path: web::Path<String>, query: web::Query<RenderQuery>) -> impl Responder {
+    let filename = match fs_utils::sanitize_filename(&path.into_inner()) {
+        Ok(name) => name,
+        Err(e) => return error_handlers::bad_request(e),
+    };
+
+    // Only text files can be rendered, binary files are a bad request rather than missing
+    let content = match read_file_content(&filename) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            return error_handlers::bad_request(format!("File '{}' is not UTF-8 text and can't be rendered", filename))
+        }
+        Err(_) => return HttpResponse::NotFound().body("File not found"),
+    };
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, Parser::new_ext(&content, Options::all()));
 
-    // Read the file content
-    match fs::read_to_string(&filepath) {
-        Ok(content) => HttpResponse::Ok().content_type("text/plain").body(content),
-        Err(_) => HttpResponse::NotFound().body("File not found"),
+    let mut response = HttpResponse::Ok();
+    response.content_type("text/html; charset=utf-8").insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+
+    // Strip unsafe tags and attributes so the output can be embedded directly
+    // Unsanitized output keeps any raw HTML from the file, so browsers must not run it as part of this origin
+    if query.sanitize {
+        html = ammonia::clean(&html);
+    } else {
+        response.insert_header((header::CONTENT_SECURITY_POLICY, "sandbox"));
     }
+
+    response.body(html)
 }
 
 // Outputs list of files within directory
@@ -44,21 +161,175 @@ async fn list_files() -> impl Responder {
     HttpResponse::Ok().json(json!(file_names))
 }
 
+// Outputs files modified after the given time, letting sync clients pull only what changed
+#[get("/list-files/since")]
+async fn list_files_since(query: web::Query<ModifiedSinceQuery>) -> impl Responder {
+    let since = match OffsetDateTime::parse(&query.timestamp, &Rfc3339) {
+        Ok(since) => since,
+        Err(e) => return error_handlers::bad_request(format!("Invalid timestamp, expected RFC 3339: {}", e)),
+    };
+
+    let directory_path = Path::new(STORAGE_PATH); //TODO: Implement group specifc file paths
+    let file_names = match read_file_names(directory_path) {
+        Ok(names) => names,
+        Err(_) => return HttpResponse::InternalServerError().body("Failed to read directory"),
+    };
+
+    // Files whose modified time can't be read are left out rather than failing the whole listing
+    let mut modified_files = Vec::new();
+    for filename in file_names {
+        let modified = match fs::metadata(directory_path.join(&filename)).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => OffsetDateTime::from(modified),
+            Err(_) => continue,
+        };
+        if modified > since {
+            let last_modified = modified.format(&Rfc3339).unwrap_or_default();
+            modified_files.push(ModifiedFile { filename, last_modified });
+        }
+    }
+
+    HttpResponse::Ok().json(modified_files)
+}
+
+// HEAD ROUTES
+
+// Checks a file exists without downloading it, reporting its size and tag so clients can tell if their copy is current
+#[head("/files/{filename:.*}")]
+async fn file_exists(path: web::Path<String>) -> impl Responder {
+    let filename = match fs_utils::sanitize_filename(&path.into_inner()) {
+        Ok(name) => name,
+        Err(e) => return error_handlers::bad_request(e),
+    };
+
+    // Read like get_file so the tag matches what a download would return
+    let content = match read_file_bytes(&filename) {
+        Ok(content) => content,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+
+    // Sized body with no data, actix writes the Content-Length and skips the body for HEAD requests
+    // Marked identity so the Compress middleware leaves it alone and the length stays the file's size
+    let empty_body = stream::empty::<Result<web::Bytes, io::Error>>();
+    let mut response = HttpResponse::Ok();
+    insert_content_headers(&mut response, &filename);
+    response
+        .insert_header(ETag(content_etag(&content)))
+        .insert_header(ContentEncoding::Identity)
+        .body(SizedStream::new(content.len() as u64, empty_body))
+}
+
 // POST ROUTES
 
 // Process to upload files to the server
-#[post("/upload/{filename}")]
-async fn upload_file(path: web::Path<String>, body: String) -> impl Responder {
-    let filename = path.into_inner();
-    let filepath = format!("{}/{}", STORAGE_PATH, filename); //TODO: Implement group specifc file paths
+// The body is stored as raw bytes so images and other binary files upload unchanged
+async fn upload_file(req: HttpRequest, path: web::Path<String>, body: web::Bytes) -> impl Responder {
+    let filename = match fs_utils::sanitize_filename(&path.into_inner()) {
+        Ok(name) => name,
+        Err(e) => return error_handlers::bad_request(e),
+    };
+
+    // Held across the If-Match check and the write so a concurrent upload can't slip in between them
+    let _write_guard = lock_storage_writes();
+
+    // Only overwrite if the client's copy matches what's on disk, when the client asks for that check
+    if let Some(if_match) = req.headers().get(header::IF_MATCH) {
+        let current_hash = read_file_bytes(&filename).ok().map(|content| calculate_content_hash(&content));
+        let matches = if_match.to_str().is_ok_and(|tags| if_match_satisfied(tags, current_hash.as_deref()));
+        if !matches {
+            return HttpResponse::Conflict().body(format!("File '{}' has changed since it was last read", filename));
+        }
+    }
 
-    // Save the file content
-    match fs::write(&filepath, body) {
-        Ok(_) => HttpResponse::Ok().body(format!("File '{}' uploaded successfully!", filename)),
+    // Save the file content, returning its new tag so the client can make its next conditional upload straight away
+    match save_file_content(&filename, &body) {
+        Ok(_) => HttpResponse::Ok()
+            .insert_header(ETag(content_etag(&body)))
+            .body(format!("File '{}' uploaded successfully!", filename)),
+        Err(e) if is_path_conflict(&e) => {
+            HttpResponse::Conflict().body(format!("'{}' clashes with an existing folder or file in its path", filename))
+        }
         Err(e) => HttpResponse::InternalServerError().body(format!("Failed to upload file: {}", e)),
     }
 }
 
+// Uploads several files in one request, reporting a result per file so one failure doesn't abort the rest
+async fn upload_batch(batch: web::Json<BatchUploadRequest>) -> impl Responder {
+    let results: Vec<BatchUploadResult> = batch
+        .into_inner()
+        .files
+        .into_iter()
+        .map(|file| {
+            let saved = fs_utils::sanitize_filename(&file.file_name).and_then(|filename| {
+                let _write_guard = lock_storage_writes();
+                save_file_content(&filename, &file.file_content)
+            });
+            match saved {
+                Ok(_) => BatchUploadResult { filename: file.file_name, status: "uploaded", error: None },
+                Err(e) => BatchUploadResult { filename: file.file_name, status: "failed", error: Some(e.to_string()) },
+            }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(results)
+}
+
+// Extracts every file in an uploaded ZIP archive into storage, reporting a result per file like batch uploads
+// Existing files are reported as conflicts and left alone unless ?overwrite=true is given
+async fn import_archive(body: web::Bytes, query: web::Query<ImportQuery>, max_uncompressed_bytes: u64) -> impl Responder {
+    // The whole archive is read before anything is written, so an oversized or corrupt archive imports nothing
+    let entries = match read_archive_entries(&body, max_uncompressed_bytes) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::FileTooLarge => return error_handlers::payload_too_large(e),
+        Err(e) => return error_handlers::bad_request(format!("Invalid ZIP archive: {}", e)),
+    };
+
+    let results: Vec<BatchUploadResult> = entries
+        .into_iter()
+        .map(|(name, content)| {
+            let imported = fs_utils::sanitize_filename(&name).and_then(|filename| {
+                // Held across the existence check and the write so the conflict check can't go stale
+                let _write_guard = lock_storage_writes();
+                let filepath = format!("{}/{}", STORAGE_PATH, filename); //TODO: Implement group specifc file paths
+                if !query.overwrite && Path::new(&filepath).exists() {
+                    return Ok(false);
+                }
+                save_file_content(&filename, &content).map(|_| true)
+            });
+            match imported {
+                Ok(true) => BatchUploadResult { filename: name, status: "imported", error: None },
+                Ok(false) => BatchUploadResult { filename: name, status: "conflict", error: Some("File already exists".to_string()) },
+                Err(e) => BatchUploadResult { filename: name, status: "failed", error: Some(e.to_string()) },
+            }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(results)
+}
+
+// Updates a file's last modified time to now without changing its content
+#[post("/files/{filename:.*}/touch")]
+async fn touch_file(path: web::Path<String>) -> impl Responder {
+    let filename = match fs_utils::sanitize_filename(&path.into_inner()) {
+        Ok(name) => name,
+        Err(e) => return error_handlers::bad_request(e),
+    };
+    let filepath = format!("{}/{}", STORAGE_PATH, filename); //TODO: Implement group specifc file paths
+
+    // Opened for writing only so the modified time can be set, the content is left untouched
+    // Only a missing file or a folder is not found, other failures such as permissions are server errors
+    let file = match fs::OpenOptions::new().write(true).open(&filepath) {
+        Ok(file) => file,
+        Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::IsADirectory) => {
+            return HttpResponse::NotFound().body("File not found")
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Failed to touch file: {}", e)),
+    };
+    match file.set_modified(SystemTime::now()) {
+        Ok(_) => HttpResponse::Ok().body(format!("File '{}' touched successfully!", filename)),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to touch file: {}", e)),
+    }
+}
+
 // PUT ROUTES
 
 
@@ -69,28 +340,654 @@ async fn upload_file(path: web::Path<String>, body: String) -> impl Responder {
 
 // Accompanying functions
 
-// Helper function to read file names from a directory
-fn read_file_names(directory: &Path) -> io::Result<Vec<String>> {
+// Helper function to read file names from a directory, files in sub-folders are named by their relative path
+pub(crate) fn read_file_names(directory: &Path) -> io::Result<Vec<String>> {
     let mut file_names = Vec::new();
+    collect_file_names(directory, directory, &mut file_names)?;
+    Ok(file_names)
+}
 
+// Recursively gathers the file names under a folder, relative to the root being listed
+fn collect_file_names(root: &Path, directory: &Path, file_names: &mut Vec<String>) -> io::Result<()> {
     // Iterate over found entries in directory
     for entry in fs::read_dir(directory)? {
         let entry = entry?;
         let path = entry.path();
 
-        // If the path is a file, attempts to retrieve the filename and convert it to a string
+        // Descend into sub-folders, symlinked folders are not followed to avoid loops
+        if entry.file_type()?.is_dir() {
+            collect_file_names(root, &path, file_names)?;
+            continue;
+        }
+
+        // If the path is a file, attempts to retrieve its path relative to the root and convert it to a string
         if path.is_file() {
-            if let Some(file_name) = path.file_name() {
-                if let Some(name_str) = file_name.to_str() {
+            if let Ok(relative_path) = path.strip_prefix(root) {
+                if let Some(name_str) = relative_path.to_str() {
                     file_names.push(name_str.to_string());
                 }
             }
         }
     }
-    Ok(file_names)
+    Ok(())
+}
+
+// Helper function to read a file's content from the storage path
+fn read_file_content(filename: &str) -> io::Result<String> {
+    let filepath = format!("{}/{}", STORAGE_PATH, filename); //TODO: Implement group specifc file paths
+    fs::read_to_string(filepath)
+}
+
+// Helper function to read a file's raw bytes from the storage path, for files that may not be text
+fn read_file_bytes(filename: &str) -> io::Result<Vec<u8>> {
+    let filepath = format!("{}/{}", STORAGE_PATH, filename); //TODO: Implement group specifc file paths
+    fs::read(filepath)
+}
+
+// Helper function to set the headers describing a stored file's type, shared by GET and HEAD
+fn insert_content_headers(response: &mut HttpResponseBuilder, filename: &str) {
+    // Serve with the type implied by the file extension, plain text when it isn't recognised
+    let content_type = mime_guess::from_path(filename).first_or_text_plain();
+    let is_text = content_type.type_() == mime_guess::mime::TEXT && content_type.get_param(mime_guess::mime::CHARSET).is_none();
+    if is_text {
+        response.content_type(format!("{}; charset=utf-8", content_type));
+    } else {
+        response.content_type(content_type.as_ref());
+    }
+
+    // Stop browsers second-guessing the type, and never let uploaded markup run as part of this origin
+    response.insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+    if ACTIVE_CONTENT_TYPES.contains(&content_type.essence_str()) {
+        let basename = filename.rsplit('/').next().unwrap_or(filename);
+        response.insert_header(ContentDisposition::attachment(basename));
+        response.insert_header((header::CONTENT_SECURITY_POLICY, "sandbox"));
+    }
+}
+
+// Helper function to read every file in a ZIP archive, failing once their combined size passes the limit
+fn read_archive_entries(archive: &[u8], max_uncompressed_bytes: u64) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut zip = zip::ZipArchive::new(io::Cursor::new(archive)).map_err(io::Error::other)?;
+    let mut entries = Vec::new();
+    let mut remaining = max_uncompressed_bytes;
+
+    for entry_index in 0..zip.len() {
+        let entry = zip.by_index(entry_index).map_err(io::Error::other)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+
+        // Sizes declared in the archive can't be trusted, so the read itself stops just past the remaining budget
+        let mut content = Vec::new();
+        entry.take(remaining.saturating_add(1)).read_to_end(&mut content)?;
+        if content.len() as u64 > remaining {
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                format!("Archive expands to more than {} bytes", max_uncompressed_bytes),
+            ));
+        }
+        remaining -= content.len() as u64;
+        entries.push((name, content));
+    }
+    Ok(entries)
+}
+
+// Helper function to write a file's content to the storage path
+fn save_file_content(filename: &str, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let filepath = format!("{}/{}", STORAGE_PATH, filename); //TODO: Implement group specifc file paths
+
+    // Create any sub-folders named in the file path
+    if let Some(parent) = Path::new(&filepath).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(filepath, content)
+}
+
+// Whether a write failed because a folder sits where the file should go, or a file sits where a folder should
+fn is_path_conflict(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::IsADirectory | io::ErrorKind::NotADirectory | io::ErrorKind::AlreadyExists)
+}
+
+// Serialises writes to storage so conditional uploads check and write without interleaving
+fn lock_storage_writes() -> MutexGuard<'static, ()> {
+    static STORAGE_WRITE_LOCK: Mutex<()> = Mutex::new(());
+    STORAGE_WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Checks an If-Match header against the current content hash, accepting quoted ETags from get_file or the bare hash
+fn if_match_satisfied(if_match: &str, current_hash: Option<&str>) -> bool {
+    let current_hash = match current_hash {
+        Some(hash) => hash,
+        None => return false,
+    };
+
+    // Weak tags never match, If-Match uses strong comparison
+    if_match.split(',').map(str::trim).any(|tag| {
+        tag == "*" || tag.strip_prefix('"').and_then(|tag| tag.strip_suffix('"')).unwrap_or(tag) == current_hash
+    })
+}
+
+// Helper function to build the strong tag for a file's content, shared by GET, HEAD and uploads
+fn content_etag(content: &[u8]) -> EntityTag {
+    EntityTag::new_strong(calculate_content_hash(content))
+}
+
+// Helper function to hash file content, used as the file's ETag
+fn calculate_content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
 }
 
 // Register routes function for easy import
-pub fn init_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(upload_file).service(get_file).service(index).service(list_files);
-}
\ No newline at end of file
+pub fn init_routes(cfg: &mut web::ServiceConfig, config: &ServerConfig) {
+    // Body size limits are attached to the upload resources only, other routes keep actix's defaults
+    cfg.service(
+        web::resource("/upload/{filename:.*}")
+            .app_data(web::PayloadConfig::new(config.max_upload_bytes))
+            .wrap(ErrorHandlers::new().handler(StatusCode::PAYLOAD_TOO_LARGE, error_handlers::render_payload_too_large))
+            .route(web::post().to(upload_file)),
+    )
+    .service(
+        web::resource("/upload-batch")
+            .app_data(web::JsonConfig::default().limit(config.max_batch_upload_bytes).error_handler(json_error_handler))
+            .route(web::post().to(upload_batch)),
+    )
+    .service({
+        let max_uncompressed_bytes = config.max_import_uncompressed_bytes;
+        web::resource("/import")
+            .app_data(web::PayloadConfig::new(config.max_import_bytes))
+            .wrap(ErrorHandlers::new().handler(StatusCode::PAYLOAD_TOO_LARGE, error_handlers::render_payload_too_large))
+            .route(web::post().to(move |body, query| import_archive(body, query, max_uncompressed_bytes)))
+    });
+    cfg.service(touch_file).service(render_file).service(get_file).service(file_exists).service(index).service(list_files).service(list_files_since);
+}
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use actix_web::test as actix_test;
+    use actix_web::body::{BodySize, MessageBody};
+    use actix_web::middleware::Compress;
+    use actix_web::App;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    // Each test works in its own folder under the storage path, removed again when it finishes
+    pub(crate) struct TestFolder(pub(crate) String);
+
+    impl TestFolder {
+        pub(crate) fn new(name: &str) -> Self {
+            let folder = format!("test-{}", name);
+            let _ = fs::remove_dir_all(format!("{}/{}", STORAGE_PATH, folder));
+            TestFolder(folder)
+        }
+
+        pub(crate) fn file(&self, name: &str) -> String {
+            format!("{}/{}", self.0, name)
+        }
+
+        pub(crate) fn write(&self, name: &str, content: &str) {
+            save_file_content(&self.file(name), content).unwrap();
+        }
+    }
+
+    impl Drop for TestFolder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(format!("{}/{}", STORAGE_PATH, self.0));
+        }
+    }
+
+    fn test_routes(cfg: &mut web::ServiceConfig) {
+        init_routes(cfg, &ServerConfig::default());
+    }
+
+    #[test]
+    fn if_match_accepts_quoted_and_bare_hashes() {
+        assert!(if_match_satisfied("\"abc\"", Some("abc")));
+        assert!(if_match_satisfied("abc", Some("abc")));
+        assert!(if_match_satisfied("\"xyz\", \"abc\"", Some("abc")));
+        assert!(if_match_satisfied("*", Some("abc")));
+    }
+
+    #[test]
+    fn if_match_rejects_stale_weak_and_missing() {
+        assert!(!if_match_satisfied("\"xyz\"", Some("abc")));
+        assert!(!if_match_satisfied("W/\"abc\"", Some("abc")));
+        assert!(!if_match_satisfied("*", None));
+        assert!(!if_match_satisfied("abc", None));
+    }
+
+    #[actix_web::test]
+    async fn upload_returns_etag_usable_for_next_conditional_upload() {
+        let folder = TestFolder::new("upload-etag");
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+        let uri = format!("/upload/{}", folder.file("note.md"));
+
+        let req = actix_test::TestRequest::post().uri(&uri).set_payload("v1").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let etag = resp.headers().get(header::ETAG).unwrap().to_str().unwrap().to_string();
+
+        let req = actix_test::TestRequest::post().uri(&uri).insert_header((header::IF_MATCH, etag.clone())).set_payload("v2").to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        // The tag for v1 is now stale
+        let req = actix_test::TestRequest::post().uri(&uri).insert_header((header::IF_MATCH, etag)).set_payload("v3").to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+        assert_eq!(fs::read_to_string(format!("{}/{}", STORAGE_PATH, folder.file("note.md"))).unwrap(), "v2");
+    }
+
+    #[actix_web::test]
+    async fn upload_routes_enforce_their_own_body_limits() {
+        let folder = TestFolder::new("upload-limits");
+        let config = ServerConfig { max_upload_bytes: 8, max_batch_upload_bytes: 128, ..ServerConfig::default() };
+        let app = actix_test::init_service(App::new().configure(|cfg| init_routes(cfg, &config))).await;
+
+        let uri = format!("/upload/{}", folder.file("note.md"));
+        let req = actix_test::TestRequest::post().uri(&uri).set_payload("12345678").to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::OK);
+        let req = actix_test::TestRequest::post().uri(&uri).set_payload("123456789").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["error"], "payload_too_large");
+
+        // The batch limit is separate from the single upload limit
+        let batch = json!({ "files": [{ "file_name": folder.file("batch.md"), "file_content": "0123456789" }] });
+        let req = actix_test::TestRequest::post().uri("/upload-batch").set_json(&batch).to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::OK);
+        let batch = json!({ "files": [{ "file_name": folder.file("batch.md"), "file_content": "x".repeat(128) }] });
+        let req = actix_test::TestRequest::post().uri("/upload-batch").set_json(&batch).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["error"], "payload_too_large");
+    }
+
+    #[actix_web::test]
+    async fn binary_files_upload_and_download_byte_for_byte() {
+        let folder = TestFolder::new("binary-download");
+        let bytes: &[u8] = &[0x89, b'P', b'N', b'G', 0xff, 0xfe, 0x00, 0x80];
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::post().uri(&format!("/upload/{}", folder.file("image.png"))).set_payload(bytes).to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let req = actix_test::TestRequest::get().uri(&format!("/files/{}", folder.file("image.png"))).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "image/png");
+        assert_eq!(resp.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+        assert_eq!(actix_test::read_body(resp).await, bytes);
+    }
+
+    #[actix_web::test]
+    async fn text_gets_charset_and_markup_is_sandboxed() {
+        let folder = TestFolder::new("content-headers");
+        save_file_content(&folder.file("notes.md"), "# Notes").unwrap();
+        save_file_content(&folder.file("page.html"), "<script>alert(1)</script>").unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::get().uri(&format!("/files/{}", folder.file("notes.md"))).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/markdown; charset=utf-8");
+        assert!(resp.headers().get(header::CONTENT_DISPOSITION).is_none());
+
+        // HEAD reports the same headers as GET
+        for method in [actix_web::http::Method::GET, actix_web::http::Method::HEAD] {
+            let req = actix_test::TestRequest::default().method(method).uri(&format!("/files/{}", folder.file("page.html"))).to_request();
+            let resp = actix_test::call_service(&app, req).await;
+            assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/html; charset=utf-8");
+            assert_eq!(resp.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"page.html\"");
+            assert_eq!(resp.headers().get(header::CONTENT_SECURITY_POLICY).unwrap(), "sandbox");
+            assert_eq!(resp.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+        }
+    }
+
+    // Builds an in-memory ZIP archive from (name, content) pairs
+    fn zip_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, content) in files {
+            writer.start_file(*name, options).unwrap();
+            io::Write::write_all(&mut writer, content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[actix_web::test]
+    async fn import_extracts_files_and_reports_conflicts() {
+        let folder = TestFolder::new("import");
+        save_file_content(&folder.file("existing.md"), "original").unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let new_file = folder.file("nested/new.md");
+        let existing = folder.file("existing.md");
+        let archive = zip_archive(&[(&new_file, b"new"), (&existing, b"replaced"), ("../escape.md", b"nope")]);
+        let req = actix_test::TestRequest::post().uri("/import").set_payload(archive.clone()).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let results: serde_json::Value = actix_test::read_body_json(resp).await;
+        let statuses: Vec<&str> = results.as_array().unwrap().iter().map(|result| result["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses, ["imported", "conflict", "failed"]);
+        assert_eq!(fs::read_to_string(format!("{}/{}", STORAGE_PATH, new_file)).unwrap(), "new");
+        assert_eq!(fs::read_to_string(format!("{}/{}", STORAGE_PATH, existing)).unwrap(), "original");
+        assert!(!Path::new("escape.md").exists());
+
+        let req = actix_test::TestRequest::post().uri("/import?overwrite=true").set_payload(archive).to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::OK);
+        assert_eq!(fs::read_to_string(format!("{}/{}", STORAGE_PATH, existing)).unwrap(), "replaced");
+    }
+
+    #[actix_web::test]
+    async fn import_rejects_oversized_and_invalid_archives() {
+        let folder = TestFolder::new("import-limits");
+        let config = ServerConfig { max_import_uncompressed_bytes: 1024, ..ServerConfig::default() };
+        let app = actix_test::init_service(App::new().configure(|cfg| init_routes(cfg, &config))).await;
+
+        // Highly compressible content stays small on the wire but expands past the cap, so nothing is written
+        let small = folder.file("small.md");
+        let archive = zip_archive(&[(&small, b"fits"), (&folder.file("bomb.md"), &[0u8; 4096])]);
+        let req = actix_test::TestRequest::post().uri("/import").set_payload(archive).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!Path::new(&format!("{}/{}", STORAGE_PATH, small)).exists());
+
+        let req = actix_test::TestRequest::post().uri("/import").set_payload("not a zip").to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn import_reports_both_size_limits_with_the_same_body() {
+        let folder = TestFolder::new("import-413");
+        let config = ServerConfig { max_import_bytes: 256, max_import_uncompressed_bytes: 1024, ..ServerConfig::default() };
+        let app = actix_test::init_service(App::new().configure(|cfg| init_routes(cfg, &config))).await;
+
+        // Over the archive size limit, rejected while reading the body
+        let req = actix_test::TestRequest::post().uri("/import").set_payload(vec![0u8; 512]).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["error"], "payload_too_large");
+
+        // Under the archive size limit but over the uncompressed cap
+        let archive = zip_archive(&[(&folder.file("bomb.md"), &[0u8; 4096])]);
+        assert!(archive.len() < 256);
+        let req = actix_test::TestRequest::post().uri("/import").set_payload(archive).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["error"], "payload_too_large");
+    }
+
+    #[test]
+    fn unbounded_uncompressed_cap_reads_entries_in_full() {
+        let archive = zip_archive(&[("a.md", b"first"), ("b.md", b"second")]);
+        let entries = read_archive_entries(&archive, u64::MAX).unwrap();
+        assert_eq!(entries, [("a.md".to_string(), b"first".to_vec()), ("b.md".to_string(), b"second".to_vec())]);
+    }
+
+    #[actix_web::test]
+    async fn unsanitized_render_is_sandboxed_and_sanitized_render_strips_scripts() {
+        let folder = TestFolder::new("render-headers");
+        save_file_content(&folder.file("x.md"), "<script>alert(document.domain)</script>").unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::get().uri(&format!("/render/{}", folder.file("x.md"))).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(header::CONTENT_SECURITY_POLICY).unwrap(), "sandbox");
+        assert_eq!(resp.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+
+        let req = actix_test::TestRequest::get().uri(&format!("/render/{}?sanitize=true", folder.file("x.md"))).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(header::X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+        assert!(!String::from_utf8(actix_test::read_body(resp).await.to_vec()).unwrap().contains("<script>"));
+    }
+
+    #[actix_web::test]
+    async fn rendering_a_binary_file_is_a_bad_request() {
+        let folder = TestFolder::new("render-binary");
+        save_file_content(&folder.file("image.md"), [0xff, 0xfe, 0x00]).unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::get().uri(&format!("/render/{}", folder.file("image.md"))).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["error"], "bad_request");
+    }
+
+    #[actix_web::test]
+    async fn get_honours_if_none_match() {
+        let folder = TestFolder::new("if-none-match");
+        save_file_content(&folder.file("note.md"), "content").unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+        let uri = format!("/files/{}", folder.file("note.md"));
+        let etag = format!("\"{}\"", calculate_content_hash(b"content"));
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(header::ETAG).unwrap().to_str().unwrap(), etag);
+
+        // The current tag, a weak form of it, or any tag at all means the client's copy is current
+        let weak = format!("W/{}", etag);
+        let list = format!("\"stale\", {}", weak);
+        for if_none_match in [etag.as_str(), weak.as_str(), list.as_str(), "*"] {
+            let req = actix_test::TestRequest::get().uri(&uri).insert_header((header::IF_NONE_MATCH, if_none_match)).to_request();
+            let resp = actix_test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::NOT_MODIFIED, "{}", if_none_match);
+            assert_eq!(resp.headers().get(header::ETAG).unwrap().to_str().unwrap(), etag);
+            assert!(actix_test::read_body(resp).await.is_empty());
+        }
+
+        let req = actix_test::TestRequest::get().uri(&uri).insert_header((header::IF_NONE_MATCH, "\"stale\"")).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(actix_test::read_body(resp).await, "content");
+    }
+
+    #[actix_web::test]
+    async fn head_sends_the_same_etag_as_get() {
+        let folder = TestFolder::new("head-etag");
+        save_file_content(&folder.file("note.md"), "content").unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+        let uri = format!("/files/{}", folder.file("note.md"));
+
+        let get = actix_test::call_service(&app, actix_test::TestRequest::get().uri(&uri).to_request()).await;
+        let head = actix_test::call_service(&app, actix_test::TestRequest::default().method(actix_web::http::Method::HEAD).uri(&uri).to_request()).await;
+        assert_eq!(head.status(), StatusCode::OK);
+        assert_eq!(head.headers().get(header::ETAG), get.headers().get(header::ETAG));
+
+        let req = actix_test::TestRequest::default().method(actix_web::http::Method::HEAD).uri("/files/a/../b").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "application/json");
+    }
+
+    #[actix_web::test]
+    async fn list_files_since_returns_only_newer_files() {
+        let folder = TestFolder::new("list-since");
+        let set_modified = |name: &str, timestamp: &str| {
+            save_file_content(&folder.file(name), name).unwrap();
+            let modified = OffsetDateTime::parse(timestamp, &Rfc3339).unwrap();
+            let file = fs::OpenOptions::new().write(true).open(format!("{}/{}", STORAGE_PATH, folder.file(name))).unwrap();
+            file.set_modified(modified.into()).unwrap();
+        };
+        set_modified("old.md", "2019-12-31T23:59:59Z");
+        set_modified("sub/new.md", "2020-01-01T00:00:01Z");
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::get().uri("/list-files/since?timestamp=2020-01-01T00:00:00Z").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Other tests share the storage folder, so only this test's files are checked
+        let listed: Vec<serde_json::Value> = actix_test::read_body_json(resp).await;
+        let ours: Vec<&serde_json::Value> =
+            listed.iter().filter(|file| file["filename"].as_str().unwrap().starts_with(&format!("{}/", folder.0))).collect();
+        assert_eq!(ours.len(), 1);
+        assert_eq!(ours[0]["filename"], folder.file("sub/new.md"));
+        assert_eq!(ours[0]["last_modified"], "2020-01-01T00:00:01Z");
+    }
+
+    #[actix_web::test]
+    async fn touch_moves_modified_time_forward_without_changing_content() {
+        let folder = TestFolder::new("touch");
+        save_file_content(&folder.file("note.md"), "content").unwrap();
+        let filepath = format!("{}/{}", STORAGE_PATH, folder.file("note.md"));
+        let long_ago = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(946_684_800);
+        fs::OpenOptions::new().write(true).open(&filepath).unwrap().set_modified(long_ago).unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::post().uri(&format!("/files/{}/touch", folder.file("note.md"))).to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::OK);
+        assert!(fs::metadata(&filepath).unwrap().modified().unwrap() > long_ago);
+        assert_eq!(fs::read_to_string(&filepath).unwrap(), "content");
+
+        // Missing files and folders are both not found
+        for name in ["missing.md", ""] {
+            let req = actix_test::TestRequest::post().uri(&format!("/files/{}/touch", folder.file(name).trim_end_matches('/'))).to_request();
+            assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND, "{}", name);
+        }
+    }
+
+    #[actix_web::test]
+    async fn batch_upload_reports_failures_without_aborting() {
+        let folder = TestFolder::new("batch-partial");
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let batch = json!({ "files": [
+            { "file_name": folder.file("good.md"), "file_content": "good" },
+            { "file_name": "../x", "file_content": "bad" },
+        ] });
+        let req = actix_test::TestRequest::post().uri("/upload-batch").set_json(&batch).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let results: serde_json::Value = actix_test::read_body_json(resp).await;
+        let statuses: Vec<&str> = results.as_array().unwrap().iter().map(|result| result["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses, ["uploaded", "failed"]);
+        assert!(results[1]["error"].is_string());
+        assert_eq!(fs::read_to_string(format!("{}/{}", STORAGE_PATH, folder.file("good.md"))).unwrap(), "good");
+    }
+
+    #[actix_web::test]
+    async fn large_text_response_is_gzipped_when_requested() {
+        let folder = TestFolder::new("gzip");
+        let content = "line of markdown text\n".repeat(5000);
+        save_file_content(&folder.file("big.txt"), &content).unwrap();
+        let app = actix_test::init_service(App::new().wrap(Compress::default()).configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::get()
+            .uri(&format!("/files/{}", folder.file("big.txt")))
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+
+        assert_eq!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/plain; charset=utf-8");
+        let compressed = actix_test::read_body(resp).await;
+        assert!(compressed.len() < content.len());
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, content);
+    }
+
+    #[actix_web::test]
+    async fn head_keeps_content_length_when_gzip_is_accepted() {
+        let folder = TestFolder::new("head-gzip");
+        let content = "line of markdown text\n".repeat(5000);
+        save_file_content(&folder.file("big.txt"), &content).unwrap();
+        let app = actix_test::init_service(App::new().wrap(Compress::default()).configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::default()
+            .method(actix_web::http::Method::HEAD)
+            .uri(&format!("/files/{}", folder.file("big.txt")))
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_ne!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        // A sized body is what actix writes out as the Content-Length header
+        assert_eq!(resp.response().body().size(), BodySize::Sized(content.len() as u64));
+    }
+
+    #[actix_web::test]
+    async fn file_named_render_downloads_and_render_route_renders() {
+        let folder = TestFolder::new("render-route");
+        save_file_content(&folder.file("render"), "# Title").unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        let req = actix_test::TestRequest::get().uri(&format!("/files/{}", folder.file("render"))).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(actix_test::read_body(resp).await, "# Title");
+
+        let req = actix_test::TestRequest::get().uri(&format!("/render/{}", folder.file("render"))).to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(String::from_utf8(actix_test::read_body(resp).await.to_vec()).unwrap().contains("<h1>Title</h1>"));
+    }
+
+    #[actix_web::test]
+    async fn upload_over_folder_or_through_file_conflicts() {
+        let folder = TestFolder::new("upload-folder");
+        save_file_content(&folder.file("notes/a.md"), "a").unwrap();
+        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+
+        // A folder already sits at the target path
+        let req = actix_test::TestRequest::post().uri(&format!("/upload/{}", folder.file("notes"))).set_payload("x").to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+
+        // A file sits where a parent folder would need to be created
+        let req = actix_test::TestRequest::post().uri(&format!("/upload/{}", folder.file("notes/a.md/b.md"))).set_payload("x").to_request();
+        assert_eq!(actix_test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn concurrent_conditional_uploads_only_one_wins() {
+        let folder = TestFolder::new("upload-race");
+        let filename = folder.file("note.md");
+        let (writer_count, rounds) = (8, 200);
+
+        // Each round resets the file, then every writer sends the same tag at once from its own thread and runtime
+        let start = Arc::new(Barrier::new(writer_count + 1));
+        let finish = Arc::new(Barrier::new(writer_count + 1));
+        let writers: Vec<_> = (0..writer_count)
+            .map(|writer| {
+                let uri = format!("/upload/{}", filename);
+                let (start, finish) = (Arc::clone(&start), Arc::clone(&finish));
+                thread::spawn(move || {
+                    actix_web::rt::System::new().block_on(async move {
+                        let app = actix_test::init_service(App::new().configure(test_routes)).await;
+                        let mut wins = Vec::new();
+                        for round in 0..rounds {
+                            start.wait();
+                            let req = actix_test::TestRequest::post()
+                                .uri(&uri)
+                                .insert_header((header::IF_MATCH, calculate_content_hash(format!("base {}", round).as_bytes())))
+                                .set_payload(format!("writer {} round {}", writer, round))
+                                .to_request();
+                            wins.push(actix_test::call_service(&app, req).await.status() == StatusCode::OK);
+                            finish.wait();
+                        }
+                        wins
+                    })
+                })
+            })
+            .collect();
+
+        for round in 0..rounds {
+            save_file_content(&filename, format!("base {}", round)).unwrap();
+            start.wait();
+            finish.wait();
+        }
+
+        let results: Vec<Vec<bool>> = writers.into_iter().map(|writer| writer.join().unwrap()).collect();
+        for round in 0..rounds {
+            let winners = results.iter().filter(|wins| wins[round]).count();
+            assert_eq!(winners, 1, "round {} had {} successful conditional writes", round, winners);
+        }
+    }
+}
@@ -1,2 +1,4 @@
 // src/routes/mod.rs
-pub mod file_routes;
\ No newline at end of file
+pub mod file_routes;
+pub mod search_routes;
+pub mod server_routes;
\ No newline at end of file
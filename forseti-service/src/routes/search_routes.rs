@@ -0,0 +1,179 @@
+// src/routes/search_routes.rs
+// standard library
+use std::fs;
+use std::io;
+use std::path::Path;
+// third-party dependencies
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+// local modules
+use super::file_routes::{read_file_names, STORAGE_PATH};
+use crate::utils::error_handlers;
+
+const SNIPPET_RADIUS: usize = 40; // bytes of context kept either side of a match
+
+// Query parameters for a content search
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    #[serde(default)]
+    case_sensitive: bool,
+}
+
+// A single line matching the search query
+#[derive(Serialize)]
+struct SearchMatch {
+    filename: String,
+    line_number: usize,
+    snippet: String,
+}
+
+// GET ROUTES
+
+// Searches the contents of stored files for a query string
+#[get("/search")]
+async fn search(query: web::Query<SearchQuery>) -> impl Responder {
+    if query.q.is_empty() {
+        return error_handlers::bad_request("Search query must not be empty");
+    }
+
+    let directory_path = Path::new(STORAGE_PATH); //TODO: Implement group specifc file paths
+
+    match scan_files(directory_path, &query.q, query.case_sensitive) {
+        Ok(matches) => HttpResponse::Ok().json(matches),
+        Err(_) => HttpResponse::InternalServerError().body("Failed to search files"),
+    }
+}
+
+// Accompanying functions
+
+// Linear scan over every file in the directory, kept apart from the route so an index can replace it later
+fn scan_files(directory: &Path, query: &str, case_sensitive: bool) -> io::Result<Vec<SearchMatch>> {
+    let mut matches = Vec::new();
+
+    for filename in read_file_names(directory)? {
+        // Files that aren't valid UTF-8 text can't match, so they are skipped
+        let content = match fs::read_to_string(directory.join(&filename)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        for (index, line) in content.lines().enumerate() {
+            if let Some((start, end)) = find_match(line, query, case_sensitive) {
+                matches.push(SearchMatch {
+                    filename: filename.clone(),
+                    line_number: index + 1,
+                    snippet: snippet_around(line, start, end - start),
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+// Helper function to find the byte range of the first match within the original line
+// Lowercasing can change a character's length (e.g. 'İ'), so case-insensitive matching compares
+// lowercased characters while remembering which original character each one came from
+fn find_match(line: &str, query: &str, case_sensitive: bool) -> Option<(usize, usize)> {
+    if case_sensitive {
+        return line.find(query).map(|position| (position, position + query.len()));
+    }
+
+    let needle: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if needle.is_empty() {
+        return Some((0, 0));
+    }
+    let lowered: Vec<(usize, usize, char)> = line
+        .char_indices()
+        .flat_map(|(offset, c)| c.to_lowercase().map(move |lower| (offset, offset + c.len_utf8(), lower)))
+        .collect();
+
+    lowered
+        .windows(needle.len())
+        .find(|window| window.iter().map(|&(_, _, lower)| lower).eq(needle.iter().copied()))
+        .map(|window| (window[0].0, window[window.len() - 1].1))
+}
+
+// Helper function to cut a snippet of the line around a match, respecting character boundaries
+fn snippet_around(line: &str, position: usize, length: usize) -> String {
+    let mut start = position.saturating_sub(SNIPPET_RADIUS).min(line.len());
+    let mut end = (position + length + SNIPPET_RADIUS).min(line.len());
+    while !line.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !line.is_char_boundary(end) {
+        end += 1;
+    }
+    line[start..end].trim().to_string()
+}
+
+// Register routes function for easy import
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(search);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::file_routes::tests::TestFolder;
+    use actix_web::test as actix_test;
+    use actix_web::App;
+    use serde_json::Value;
+
+    #[test]
+    fn case_insensitive_match_maps_back_to_original_line() {
+        // 'İ' is two bytes but lowercases to three, which used to shift the match past the needle
+        let line = "İİİİ Forseti notes";
+        let (start, end) = find_match(line, "forseti", false).unwrap();
+        assert_eq!(&line[start..end], "Forseti");
+        assert!(snippet_around(line, start, end - start).contains("Forseti"));
+    }
+
+    #[test]
+    fn case_insensitive_match_covers_whole_characters() {
+        let line = "prefix STRASSE suffix";
+        let (start, end) = find_match(line, "strasse", false).unwrap();
+        assert_eq!(&line[start..end], "STRASSE");
+
+        let line = "abc İstanbul";
+        let (start, end) = find_match(line, "i\u{307}stanbul", false).unwrap();
+        assert_eq!(&line[start..end], "İstanbul");
+    }
+
+    #[test]
+    fn case_sensitive_match_is_exact() {
+        assert_eq!(find_match("Forseti forseti", "forseti", true), Some((8, 15)));
+        assert_eq!(find_match("FORSETI", "forseti", true), None);
+        assert_eq!(find_match("FORSETI", "forseti", false), Some((0, 7)));
+    }
+
+    // Other tests share the storage folder, so only matches in this test's folder are kept, sorted by filename
+    fn matches_in(folder: &TestFolder, matches: Vec<Value>) -> Vec<Value> {
+        let prefix = format!("{}/", folder.0);
+        let mut ours: Vec<Value> = matches.into_iter().filter(|found| found["filename"].as_str().unwrap().starts_with(&prefix)).collect();
+        ours.sort_by_key(|found| found["filename"].as_str().unwrap().to_string());
+        ours
+    }
+
+    #[actix_web::test]
+    async fn search_route_reports_filename_line_and_snippet() {
+        let folder = TestFolder::new("search");
+        folder.write("notes/today.md", "# Today\nnothing here\nMet the Zebracorn team for lunch\n");
+        folder.write("other.md", "a zebracorn in lower case\n");
+        let app = actix_test::init_service(App::new().configure(init_routes)).await;
+
+        let req = actix_test::TestRequest::get().uri("/search?q=zebracorn").to_request();
+        let ours = matches_in(&folder, actix_test::call_and_read_body_json(&app, req).await);
+        assert_eq!(ours.len(), 2);
+        assert_eq!(ours[0]["filename"], folder.file("notes/today.md"));
+        assert_eq!(ours[0]["line_number"], 3);
+        assert_eq!(ours[0]["snippet"], "Met the Zebracorn team for lunch");
+        assert_eq!(ours[1]["filename"], folder.file("other.md"));
+        assert_eq!(ours[1]["line_number"], 1);
+
+        let req = actix_test::TestRequest::get().uri("/search?q=Zebracorn&case_sensitive=true").to_request();
+        let ours = matches_in(&folder, actix_test::call_and_read_body_json(&app, req).await);
+        assert_eq!(ours.len(), 1);
+        assert_eq!(ours[0]["filename"], folder.file("notes/today.md"));
+    }
+}
@@ -0,0 +1,35 @@
+// src/routes/server_routes.rs
+// third-party dependencies
+use actix_web::{get, web, HttpResponse, Responder};
+use serde_json::json;
+// local modules
+use crate::config::ServerConfig;
+
+// GET ROUTES
+
+// Describes the server's effective configuration and supported features so clients can adapt their UI
+#[get("/capabilities")]
+async fn capabilities(config: web::Data<ServerConfig>) -> impl Responder {
+    HttpResponse::Ok().json(json!({
+        "config": config.get_ref(),
+        "tls_enabled": config.tls_cert_path.is_some(),
+        "compression": true,
+        "features": {
+            "authentication": false,
+            "teams": false,
+            "versioning": false,
+            "locking": false,
+            "folders": true,
+            "search": true,
+            "markdown_render": true,
+            "batch_upload": true,
+            "zip_import": true,
+            "conditional_requests": true,
+        },
+    }))
+}
+
+// Register routes function for easy import
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(capabilities);
+}
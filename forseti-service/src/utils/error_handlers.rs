@@ -0,0 +1,94 @@
+// src/utils/error_handlers.rs
+// standard library
+use std::fmt::Display;
+// third-party dependencies
+use actix_web::dev::ServiceResponse;
+use actix_web::error::{InternalError, JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::middleware::ErrorHandlerResponse;
+use actix_web::{HttpRequest, HttpResponse};
+use serde_json::json;
+
+// Builds the bad request body shared by every 400 response, so clients can handle them all the same way
+pub fn bad_request(message: impl Display) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({ "error": "bad_request", "message": message.to_string() }))
+}
+
+// Builds the body shared by every 413 response, whether the limit was hit reading the body or after it
+pub fn payload_too_large(message: impl Display) -> HttpResponse {
+    HttpResponse::PayloadTooLarge().json(json!({ "error": "payload_too_large", "message": message.to_string() }))
+}
+
+// Builds the body for requests cut off by the handling timeout, matching the other error bodies
+pub fn request_timeout(message: impl Display) -> HttpResponse {
+    HttpResponse::RequestTimeout().json(json!({ "error": "request_timeout", "message": message.to_string() }))
+}
+
+// Replaces actix's plain text 413 for raw bodies over their PayloadConfig limit with the shared JSON body
+pub fn render_payload_too_large<B>(res: ServiceResponse<B>) -> actix_web::Result<ErrorHandlerResponse<B>> {
+    let (req, res) = res.into_parts();
+    let message = res.error().map(|e| e.to_string()).unwrap_or_else(|| "Payload too large".to_string());
+    Ok(ErrorHandlerResponse::Response(ServiceResponse::new(req, payload_too_large(message)).map_into_right_body()))
+}
+
+// Turns malformed JSON bodies into a consistent bad request response naming the offending field
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    // Oversized bodies keep their 413 status rather than being reported as malformed
+    let response = match err {
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => payload_too_large(&err),
+        _ => bad_request(&err),
+    };
+    InternalError::from_response(err, response).into()
+}
+
+// Reports missing or malformed query parameters with the same body as other bad requests
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let response = bad_request(&err);
+    InternalError::from_response(err, response).into()
+}
+
+// Reports path segments that don't fit the route with the same body as other bad requests
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    let response = bad_request(&err);
+    InternalError::from_response(err, response).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::routes::{file_routes, search_routes};
+    use actix_web::test as actix_test;
+    use actix_web::{http::StatusCode, web, App};
+    use serde_json::Value;
+
+    async fn number(path: web::Path<u32>) -> HttpResponse {
+        HttpResponse::Ok().body(path.to_string())
+    }
+
+    async fn assert_bad_request(uri: &str) {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .app_data(web::PathConfig::default().error_handler(path_error_handler))
+                .configure(|cfg| file_routes::init_routes(cfg, &ServerConfig::default()))
+                .configure(search_routes::init_routes)
+                .route("/numbers/{number}", web::get().to(number)),
+        )
+        .await;
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri(uri).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        let body: Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["error"], "bad_request", "{}", uri);
+        assert!(body["message"].as_str().is_some_and(|message| !message.is_empty()), "{}", uri);
+    }
+
+    #[actix_web::test]
+    async fn query_path_and_handler_errors_share_a_json_body() {
+        assert_bad_request("/search").await; // missing query parameter
+        assert_bad_request("/search?q=").await; // rejected by the handler
+        assert_bad_request("/numbers/abc").await; // path segment of the wrong type
+        assert_bad_request("/files/a/../b").await; // rejected by the file name sanitizer
+        assert_bad_request("/list-files/since?timestamp=yesterday").await;
+    }
+}
@@ -0,0 +1,76 @@
+// src/utils/fs_utils.rs
+// standard library
+use std::io;
+
+// Rejects user supplied file paths that could escape the storage directory, sub-folders are allowed
+pub fn sanitize_filename(name: &str) -> io::Result<String> {
+    if name.trim().is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "File name must not be empty"));
+    }
+
+    // Backslashes, parent references and empty or "." segments (which cover absolute paths) would let the name point outside the storage path
+    let has_unsafe_segment = name.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..");
+    if has_unsafe_segment || name.contains(['\\', '\0']) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid file name {:?}", name)));
+    }
+
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rejected(name: &str) {
+        let err = sanitize_filename(name).expect_err(name);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", name);
+    }
+
+    #[test]
+    fn rejects_parent_directory_traversal() {
+        assert_rejected("../x");
+        assert_rejected("a/../../x");
+        assert_rejected("..");
+    }
+
+    #[test]
+    fn rejects_absolute_and_backslash_paths() {
+        assert_rejected("/etc/passwd");
+        assert_rejected("a\\..\\b");
+        assert_rejected("a\\b");
+    }
+
+    #[test]
+    fn rejects_empty_and_dot_segments() {
+        assert_rejected("a//b");
+        assert_rejected("./x");
+        assert_rejected("a/./b");
+        assert_rejected("a/");
+    }
+
+    #[test]
+    fn rejects_null_bytes() {
+        assert_rejected("a\0b");
+        assert_rejected("notes.md\0.txt");
+    }
+
+    #[test]
+    fn rejects_empty_and_whitespace_names() {
+        assert_rejected("");
+        assert_rejected("   ");
+        assert_rejected("\t\n");
+    }
+
+    #[test]
+    fn accepts_plain_and_nested_names() {
+        assert_eq!(sanitize_filename("notes.md").unwrap(), "notes.md");
+        assert_eq!(sanitize_filename("projects/2026/notes.md").unwrap(), "projects/2026/notes.md");
+    }
+
+    #[test]
+    fn accepts_dots_inside_segments() {
+        assert_eq!(sanitize_filename("v1..2.md").unwrap(), "v1..2.md");
+        assert_eq!(sanitize_filename("drafts/...md").unwrap(), "drafts/...md");
+        assert_eq!(sanitize_filename("..hidden/notes.md").unwrap(), "..hidden/notes.md");
+    }
+}
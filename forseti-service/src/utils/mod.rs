@@ -0,0 +1,4 @@
+// src/utils/mod.rs
+pub mod error_handlers;
+pub mod fs_utils;
+pub mod rate_limiter;
\ No newline at end of file
@@ -0,0 +1,206 @@
+// src/utils/rate_limiter.rs
+// standard library
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+// third-party dependencies
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header;
+use actix_web::{Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+
+const EXEMPT_PATHS: [&str; 2] = ["/health", "/metrics"]; // monitoring endpoints are never throttled
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60); // idle buckets are swept at most this often
+
+// Request allowance for a single client, refilled continuously up to one minute's worth
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+// Buckets for every tracked client, plus when the idle ones were last swept
+struct ClientBuckets {
+    buckets: HashMap<String, TokenBucket>,
+    last_pruned: Instant,
+}
+
+// Token bucket rate limiter keyed by client IP, a limit of zero disables it
+#[derive(Clone)]
+pub struct RateLimiter {
+    requests_per_minute: u32,
+    clients: Arc<Mutex<ClientBuckets>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> Self {
+        let clients = ClientBuckets { buckets: HashMap::new(), last_pruned: Instant::now() };
+        RateLimiter { requests_per_minute, clients: Arc::new(Mutex::new(clients)) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.requests_per_minute > 0
+    }
+
+    // Takes a token for the client, or returns the number of seconds until one is available
+    fn try_acquire(&self, client: &str) -> Result<(), u64> {
+        let capacity = self.requests_per_minute as f64;
+        let refill_per_second = capacity / 60.0;
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // Clients idle for a minute have a full bucket again, so forgetting them changes nothing
+        // Swept once per interval so the cost is spread over every request in that interval
+        if now.duration_since(clients.last_pruned) >= PRUNE_INTERVAL {
+            clients.buckets.retain(|_, bucket| now.duration_since(bucket.last_refill) < PRUNE_INTERVAL);
+            clients.last_pruned = now;
+        }
+
+        let bucket = clients
+            .buckets
+            .entry(client.to_string())
+            .or_insert(TokenBucket { tokens: capacity, last_refill: now });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_second).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / refill_per_second).ceil() as u64)
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimiterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware { service, limiter: self.clone() }))
+    }
+}
+
+pub struct RateLimiterMiddleware<S> {
+    service: S,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimiterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if self.limiter.is_enabled() && !EXEMPT_PATHS.contains(&req.path()) {
+            // Keyed on the socket address, forwarding headers are client controlled and could dodge the limit
+            let client = req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default();
+
+            if let Err(retry_after) = self.limiter.try_acquire(&client) {
+                let response = HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, retry_after))
+                    .body("Too many requests, please try again later");
+                return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+            }
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test as actix_test;
+    use actix_web::{http::StatusCode, web, App};
+
+    #[test]
+    fn burst_past_limit_is_rejected_with_retry_after() {
+        let limiter = RateLimiter::new(5);
+        for _ in 0..5 {
+            assert_eq!(limiter.try_acquire("10.0.0.1"), Ok(()));
+        }
+
+        // One token refills every 12 seconds at 5 requests per minute
+        let retry_after = limiter.try_acquire("10.0.0.1").unwrap_err();
+        assert!((1..=12).contains(&retry_after), "retry after {}", retry_after);
+    }
+
+    #[test]
+    fn clients_have_separate_buckets() {
+        let limiter = RateLimiter::new(1);
+        assert_eq!(limiter.try_acquire("10.0.0.1"), Ok(()));
+        assert!(limiter.try_acquire("10.0.0.1").is_err());
+        assert_eq!(limiter.try_acquire("10.0.0.2"), Ok(()));
+    }
+
+    #[test]
+    fn idle_clients_are_pruned_after_the_interval() {
+        let limiter = RateLimiter::new(1);
+        limiter.try_acquire("10.0.0.1").unwrap();
+        {
+            let mut clients = limiter.clients.lock().unwrap();
+            let long_ago = Instant::now() - PRUNE_INTERVAL * 2;
+            clients.buckets.get_mut("10.0.0.1").unwrap().last_refill = long_ago;
+            clients.last_pruned = long_ago;
+        }
+
+        limiter.try_acquire("10.0.0.2").unwrap();
+        let clients = limiter.clients.lock().unwrap();
+        assert!(!clients.buckets.contains_key("10.0.0.1"));
+        assert!(clients.buckets.contains_key("10.0.0.2"));
+    }
+
+    #[actix_web::test]
+    async fn middleware_throttles_routes_but_not_exempt_paths() {
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(RateLimiter::new(2))
+                .route("/ping", web::get().to(HttpResponse::Ok))
+                .route("/health", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/ping").to_request()).await;
+            statuses.push(resp.status());
+            if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+                assert!(resp.headers().contains_key(header::RETRY_AFTER));
+            }
+        }
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]);
+
+        for _ in 0..5 {
+            let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/health").to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+    }
+
+    #[actix_web::test]
+    async fn disabled_limiter_never_throttles() {
+        let app = actix_test::init_service(App::new().wrap(RateLimiter::new(0)).route("/ping", web::get().to(HttpResponse::Ok))).await;
+        for _ in 0..20 {
+            let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/ping").to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+    }
+}